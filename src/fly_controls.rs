@@ -0,0 +1,144 @@
+use bevy::input::mouse::MouseMotion;
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+
+use crate::CursorGrab;
+
+/// Amount the accumulated mouse motion is scaled by before being applied to the
+/// look angles. One reported "dot" of motion rotates the view by
+/// `sensitivity * RADIANS_PER_DOT` radians.
+const RADIANS_PER_DOT: f32 = 1.0 / 180.0;
+
+/// Valorant-style free-look / fly camera that coexists with the
+/// [`OrbitCamera`](crate::orbit_controls::OrbitCamera). While `enabled` it
+/// uses WASD + Space/Shift for movement, and raw mouse motion for pitch/yaw
+/// while the cursor is grabbed (see [`CursorGrab`]).
+#[derive(Component)]
+pub struct FlyCamera {
+    pub sensitivity: f32,
+    pub move_speed: f32,
+    pub friction: f32,
+    pub pitch: f32,
+    pub yaw: f32,
+    pub velocity: Vec3,
+    pub enabled: bool,
+    pub key_forward: KeyCode,
+    pub key_backward: KeyCode,
+    pub key_left: KeyCode,
+    pub key_right: KeyCode,
+    pub key_up: KeyCode,
+    pub key_down: KeyCode,
+    pub key_run: KeyCode,
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        FlyCamera {
+            sensitivity: 3.0,
+            move_speed: 12.0,
+            friction: 0.5,
+            pitch: 0.0,
+            yaw: 0.0,
+            velocity: Vec3::ZERO,
+            enabled: false,
+            key_forward: KeyCode::KeyW,
+            key_backward: KeyCode::KeyS,
+            key_left: KeyCode::KeyA,
+            key_right: KeyCode::KeyD,
+            key_up: KeyCode::Space,
+            key_down: KeyCode::ShiftLeft,
+            key_run: KeyCode::ControlLeft,
+        }
+    }
+}
+
+pub struct FlyCameraPlugin;
+impl FlyCameraPlugin {
+    fn mouse_motion_system(
+        cursor_grab: Res<CursorGrab>,
+        mut mouse_motion_events: EventReader<MouseMotion>,
+        mut query: Query<(&mut FlyCamera, &mut Transform, &mut Camera)>,
+    ) {
+        // Only look around while the cursor is grabbed, so moving the mouse
+        // freely over the window does not spin the view.
+        if !cursor_grab.grabbed {
+            mouse_motion_events.clear();
+            return;
+        }
+
+        let mut delta = Vec2::ZERO;
+        for event in mouse_motion_events.read() {
+            delta += event.delta;
+        }
+        for (mut camera, mut transform, _) in query.iter_mut() {
+            if !camera.enabled {
+                continue;
+            }
+
+            camera.yaw -= delta.x * camera.sensitivity * RADIANS_PER_DOT;
+            camera.pitch -= delta.y * camera.sensitivity * RADIANS_PER_DOT;
+
+            // Clamp the pitch just shy of straight up/down to avoid gimbal flip.
+            let limit = std::f32::consts::FRAC_PI_2 - 0.01;
+            camera.pitch = camera.pitch.clamp(-limit, limit);
+
+            transform.rotation =
+                Quat::from_euler(EulerRot::YXZ, camera.yaw, camera.pitch, 0.0);
+        }
+    }
+
+    fn movement_system(
+        time: Res<Time>,
+        keyboard_input: Res<ButtonInput<KeyCode>>,
+        mut query: Query<(&mut FlyCamera, &mut Transform, &mut Camera)>,
+    ) {
+        let dt = time.delta_seconds();
+        for (mut camera, mut transform, _) in query.iter_mut() {
+            if !camera.enabled {
+                continue;
+            }
+
+            // Assemble the requested direction in camera-local space.
+            let mut axis = Vec3::ZERO;
+            if keyboard_input.pressed(camera.key_forward) {
+                axis -= Vec3::Z;
+            }
+            if keyboard_input.pressed(camera.key_backward) {
+                axis += Vec3::Z;
+            }
+            if keyboard_input.pressed(camera.key_left) {
+                axis -= Vec3::X;
+            }
+            if keyboard_input.pressed(camera.key_right) {
+                axis += Vec3::X;
+            }
+            if keyboard_input.pressed(camera.key_up) {
+                axis += Vec3::Y;
+            }
+            if keyboard_input.pressed(camera.key_down) {
+                axis -= Vec3::Y;
+            }
+
+            let run = if keyboard_input.pressed(camera.key_run) {
+                3.0
+            } else {
+                1.0
+            };
+
+            // Rotate the local direction into world space and integrate a velocity
+            // with a per-frame friction term so movement eases in and out.
+            let direction = transform.rotation * axis;
+            let friction = camera.friction;
+            let move_speed = camera.move_speed;
+            camera.velocity += direction * move_speed * run * dt;
+            camera.velocity *= 1.0 - friction;
+
+            transform.translation += camera.velocity * dt;
+        }
+    }
+}
+impl Plugin for FlyCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (Self::mouse_motion_system, Self::movement_system));
+    }
+}
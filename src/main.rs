@@ -1,62 +1,168 @@
 use std::env;
 
 use bevy::{
-    diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
+    app::AppExit,
+    asset::LoadState,
+    core_pipeline::Skybox,
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     prelude::*,
+    render::render_resource::{TextureViewDescriptor, TextureViewDimension},
+    window::{CursorGrabMode, PrimaryWindow},
 };
 use rand::distributions::{Distribution, Uniform};
+mod fly_controls;
 mod orbit_controls;
+use fly_controls::{FlyCamera, FlyCameraPlugin};
 use orbit_controls::{OrbitCamera, OrbitCameraPlugin};
 
-#[derive(Default)]
+#[derive(Resource, Default)]
 struct StartupOptions{
     box_count: i32,
+    fly_camera: bool,
+    scene_path: Option<String>,
+    scene_instances: i32,
+    skybox_path: Option<String>,
+    benchmark: bool,
+    benchmark_csv: Option<String>,
 }
 
+#[derive(Component)]
 struct FpsText;
 
+/// Tracks a glTF scene loaded from the command line. The scene is spawned by the
+/// `SceneSpawner` over several frames, so its embedded cameras are gathered
+/// lazily (see `scene_camera_system`) rather than the moment loading finishes.
+#[derive(Resource, Default)]
+struct SceneState {
+    handle: Option<Handle<Scene>>,
+    cameras_collected: bool,
+    cameras: Vec<Entity>,
+    active_camera: usize,
+}
+
+/// Tracks a cubemap image loaded from the command line. The PNG is loaded as a
+/// flat image and only reinterpreted as a cube texture once it has finished
+/// loading (see `skybox_asset_system`).
+#[derive(Resource, Default)]
+struct CubemapState {
+    handle: Option<Handle<Image>>,
+    is_loaded: bool,
+}
+
+/// Number of frames the deterministic benchmark sweep runs for.
+const BENCHMARK_FRAMES: usize = 1800;
+
+/// Drives the automated benchmark: when `running`, the OrbitCamera is swept
+/// along a fixed path and each frame's duration is recorded so runs are
+/// comparable regardless of user input.
+#[derive(Resource, Default)]
+struct BenchmarkState {
+    running: bool,
+    frame: usize,
+    total_frames: usize,
+    samples: Vec<f32>,
+    csv_path: Option<String>,
+}
+
+/// Whether the cursor is currently grabbed to (and hidden inside) the window.
+/// The camera systems read this to decide whether mouse motion should rotate the
+/// view without a button held.
+#[derive(Resource, Default)]
+pub struct CursorGrab {
+    pub grabbed: bool,
+}
+
 fn init(
-	commands: &mut Commands,
+	mut commands: Commands,
 	mut meshes: ResMut<Assets<Mesh>>,
 	mut materials: ResMut<Assets<StandardMaterial>>,
 	asset_server: Res<AssetServer>,
-	startup_command: ResMut<StartupOptions>,
+	startup_command: Res<StartupOptions>,
+	mut scene_state: ResMut<SceneState>,
+	mut cubemap_state: ResMut<CubemapState>,
 ) {
-	commands
-		.spawn(LightBundle {
-			transform: Transform::from_translation(Vec3::new(4.0, 8.0, 4.0)),
+	commands.spawn(PointLightBundle {
+		point_light: PointLight {
+			shadows_enabled: true,
 			..Default::default()
-		})
-		.spawn(Camera3dBundle {
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 10 as f32 * 1.25))
-                .looking_at(Vec3::default(), Vec3::unit_y()),
-            ..Default::default()
-        })
-		.with(OrbitCamera::new(0.0, 0.0, 10 as f32 * 1.25, Vec3::zero()));
-
-	commands.spawn(CameraUiBundle::default())
-	// texture
-	.spawn(TextBundle {
-		transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
-		style: Style {
-			align_self: AlignSelf::FlexEnd,
+		},
+		transform: Transform::from_translation(Vec3::new(4.0, 8.0, 4.0)),
+		..Default::default()
+	});
+
+	let mut camera = commands.spawn((
+		Camera3dBundle {
+			transform: Transform::from_translation(Vec3::new(0.0, 0.0, 10_f32 * 1.25))
+				.looking_at(Vec3::ZERO, Vec3::Y),
+			..Default::default()
+		},
+		OrbitCamera {
+			enabled: !startup_command.fly_camera && !startup_command.benchmark,
+			..OrbitCamera::new(0.0, 0.0, 10_f32 * 1.25, Vec3::ZERO)
+		},
+		FlyCamera {
+			enabled: startup_command.fly_camera,
 			..Default::default()
 		},
-		text: Text {
-			value: " FPS:".to_string(),
-			font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-			style: TextStyle {
+	));
+
+	// Optional cubemap background. The image is attached to the camera up front;
+	// it is only usable as a skybox once `skybox_asset_system` has reinterpreted
+	// it as a cube texture.
+	if let Some(path) = startup_command.skybox_path.clone() {
+		let skybox_handle: Handle<Image> = asset_server.load(path);
+		cubemap_state.handle = Some(skybox_handle.clone());
+		camera.insert(Skybox {
+			image: skybox_handle,
+			brightness: 1000.0,
+		});
+	}
+
+	commands.spawn((
+		TextBundle::from_section(
+			" FPS:",
+			TextStyle {
+				font: asset_server.load("fonts/FiraSans-Bold.ttf"),
 				font_size: 20.0,
 				color: Color::WHITE,
-				..Default::default()
 			},
-		},
-		..Default::default()
-	})
-	.with(FpsText);
+		)
+		.with_style(Style {
+			align_self: AlignSelf::FlexEnd,
+			..Default::default()
+		}),
+		FpsText,
+	));
 
-	let box_mesh = meshes.add(Mesh::from(shape::Box::new(0.9, 0.9, 0.9)));
-	//let box_material = materials.add(Color::rgb(1.0, 0.2, 0.3).into());
+	// When a glTF/glb path is supplied we benchmark that scene instead of the
+	// synthetic box grid, instancing it on a grid so the same model stresses the
+	// renderer. The handle is kept around so its embedded cameras can be
+	// collected once the scene has spawned (see `scene_camera_system`).
+	if let Some(path) = startup_command.scene_path.clone() {
+		let scene_handle: Handle<Scene> = asset_server.load(scene_asset_path(&path));
+		scene_state.handle = Some(scene_handle.clone());
+
+		let count = startup_command.scene_instances.max(1);
+		let side = (count as f32).sqrt().ceil() as i32;
+		let spacing = 4.0;
+		for i in 0..count {
+			let x = i % side - side / 2;
+			let z = i / side - side / 2;
+			commands.spawn(SceneBundle {
+				scene: scene_handle.clone(),
+				transform: Transform::from_translation(Vec3::new(
+					x as f32 * spacing,
+					0.0,
+					z as f32 * spacing,
+				)),
+				..Default::default()
+			});
+		}
+
+		return;
+	}
+
+	let box_mesh = meshes.add(Cuboid::new(0.9, 0.9, 0.9));
 
 	let box_colors: [Color; 3] = [
 		Color::rgb(1.0, 0.2, 0.3),
@@ -64,12 +170,6 @@ fn init(
 		Color::rgb(0.2, 0.3, 1.0)
 	];
 
-	let mut box_materials: Vec<Handle<StandardMaterial>> = Vec::new();
-
-	for color in box_colors.iter(){
-		box_materials.push(materials.add((*color).into()));
-	}
-
 	let values = Uniform::new(0, 3);
 
 	let amount: i32 = startup_command.box_count;
@@ -78,11 +178,9 @@ fn init(
 		for y in -(amount / 2)..(amount / 2) {
 			for z in -(amount / 2)..(amount / 2) {
 				let mut rng = rand::thread_rng();
-				//let current_material = box_materials[values.sample(&mut rng)].clone_weak() as Handle<StandardMaterial>;
-				let current_material = materials.add(box_colors[values.sample(&mut rng)].into());
+				let current_material = materials.add(box_colors[values.sample(&mut rng)]);
 				commands.spawn(PbrBundle {
 					mesh: box_mesh.clone(),
-					//material: box_material.clone(),
 					material: current_material,
 					transform: Transform::from_translation(Vec3::new(
 						x as f32, y as f32, z as f32,
@@ -94,46 +192,330 @@ fn init(
 	}
 }
 
-fn text_update_system(diagnostics: Res<Diagnostics>, mut query: Query<&mut Text, With<FpsText>>) {
+/// Resolve a user-supplied glTF/glb path to the asset path of its first scene.
+/// A path that already carries a `#Label` is passed through untouched.
+fn scene_asset_path(path: &str) -> String {
+    if path.contains('#') {
+        path.to_string()
+    } else {
+        format!("{}#Scene0", path)
+    }
+}
+
+fn text_update_system(diagnostics: Res<DiagnosticsStore>, mut query: Query<&mut Text, With<FpsText>>) {
     for mut text in query.iter_mut() {
-        if let Some(fps) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
-            if let Some(average) = fps.average() {
-                text.value = format!(" FPS: {:.0}", average);
+        if let Some(fps) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS) {
+            if let Some(average) = fps.smoothed() {
+                text.sections[0].value = format!(" FPS: {:.0}", average);
             }
         }
     }
 }
 
+fn toggle_camera_mode(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<(&mut OrbitCamera, &mut FlyCamera, &Transform)>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyF) {
+        for (mut orbit, mut fly, transform) in query.iter_mut() {
+            fly.enabled = !fly.enabled;
+            orbit.enabled = !fly.enabled;
+
+            if fly.enabled {
+                // Seed the look angles from the current orientation so the view
+                // does not snap when switching into the freecam.
+                let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+                fly.yaw = yaw;
+                fly.pitch = pitch;
+            } else {
+                // Recover the orbit parameters from the current pose so the
+                // orbit camera picks up where the freecam left off.
+                let offset = transform.translation - orbit.center;
+                orbit.distance = offset.length();
+                let dir = offset.normalize();
+                orbit.y = dir.y.clamp(-1.0, 1.0).acos();
+                orbit.x = (-dir.x).atan2(-dir.z);
+            }
+        }
+    }
+}
+
+/// Gather the cameras embedded in the command-line glTF scene once they have
+/// actually been spawned, then let the user cycle through them — plus the
+/// user-controlled OrbitCamera — by pressing `C`, wrapping back to the orbit
+/// camera at the end of the list.
+fn scene_camera_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut scene_state: ResMut<SceneState>,
+    orbit_cameras: Query<Entity, With<OrbitCamera>>,
+    scene_cameras: Query<Entity, (With<Camera>, Without<OrbitCamera>)>,
+    mut cameras: Query<&mut Camera>,
+) {
+    // The scene is spawned asynchronously, so keep re-scanning until its cameras
+    // appear rather than latching on a single load-state check.
+    if scene_state.handle.is_some() && !scene_state.cameras_collected {
+        let spawned: Vec<Entity> = scene_cameras.iter().collect();
+        if !spawned.is_empty() {
+            // The OrbitCamera always comes first so that cycling eventually wraps
+            // back to it; the scene's own cameras start out disabled.
+            let mut list: Vec<Entity> = orbit_cameras.iter().collect();
+            for entity in &spawned {
+                if let Ok(mut camera) = cameras.get_mut(*entity) {
+                    camera.is_active = false;
+                }
+            }
+            list.extend(spawned);
+            scene_state.cameras = list;
+            scene_state.cameras_collected = true;
+        }
+    }
+
+    if scene_state.cameras.len() > 1 && keyboard_input.just_pressed(KeyCode::KeyC) {
+        let current = scene_state.cameras[scene_state.active_camera];
+        if let Ok(mut camera) = cameras.get_mut(current) {
+            camera.is_active = false;
+        }
+
+        scene_state.active_camera = (scene_state.active_camera + 1) % scene_state.cameras.len();
+
+        let next = scene_state.cameras[scene_state.active_camera];
+        if let Ok(mut camera) = cameras.get_mut(next) {
+            camera.is_active = true;
+        }
+    }
+}
+
+/// Once the cubemap PNG has finished loading, reinterpret it as a cube texture
+/// (six array layers, a `Cube` texture view) so it can be used as the skybox
+/// attached to the camera in `init`. Only a vertically stacked six-face strip is
+/// supported — a horizontal cross would need to be rearranged first.
+fn skybox_asset_system(
+    asset_server: Res<AssetServer>,
+    mut images: ResMut<Assets<Image>>,
+    mut cubemap_state: ResMut<CubemapState>,
+) {
+    let handle = match &cubemap_state.handle {
+        Some(handle) => handle.clone(),
+        None => return,
+    };
+
+    if cubemap_state.is_loaded
+        || asset_server.get_load_state(&handle) != Some(LoadState::Loaded)
+    {
+        return;
+    }
+
+    if let Some(image) = images.get_mut(&handle) {
+        image.reinterpret_stacked_2d_as_array(6);
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            dimension: Some(TextureViewDimension::Cube),
+            ..Default::default()
+        });
+        cubemap_state.is_loaded = true;
+    }
+}
+
+/// Toggle an immersive mouse-look mode by pressing Escape or Tab: the cursor is
+/// locked to the primary window and hidden, letting the camera systems consume
+/// raw mouse motion without a button held. Toggling back restores the cursor.
+fn cursor_grab_system(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut cursor_grab: ResMut<CursorGrab>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        || keyboard_input.just_pressed(KeyCode::Tab)
+    {
+        let mut window = match windows.get_single_mut() {
+            Ok(window) => window,
+            Err(_) => return,
+        };
+
+        cursor_grab.grabbed = !cursor_grab.grabbed;
+        if cursor_grab.grabbed {
+            window.cursor.grab_mode = CursorGrabMode::Locked;
+            window.cursor.visible = false;
+        } else {
+            window.cursor.grab_mode = CursorGrabMode::None;
+            window.cursor.visible = true;
+        }
+    }
+}
+
+/// Drive the OrbitCamera along a fixed, deterministic path and record the
+/// duration of every frame it produces. `camera.x` sweeps a full turn while the
+/// polar angle and zoom ease, so each run exercises the same workload. When the
+/// configured number of frames is reached the statistics are printed and the
+/// app exits.
+fn benchmark_system(
+    diagnostics: Res<DiagnosticsStore>,
+    mut benchmark: ResMut<BenchmarkState>,
+    mut query: Query<(&mut OrbitCamera, &mut Transform)>,
+    mut app_exit: EventWriter<AppExit>,
+) {
+    if !benchmark.running {
+        return;
+    }
+
+    if benchmark.frame >= benchmark.total_frames {
+        benchmark.running = false;
+        report_benchmark(&benchmark);
+        app_exit.send(AppExit);
+        return;
+    }
+
+    let t = benchmark.frame as f32 / benchmark.total_frames as f32;
+    for (mut camera, mut transform) in query.iter_mut() {
+        camera.x = t * std::f32::consts::TAU;
+        camera.y = 0.4 + 0.3 * (t * std::f32::consts::PI).sin();
+        camera.distance = 20.0 + 60.0 * t;
+
+        let rot = Quat::from_axis_angle(Vec3::Y, camera.x)
+            * Quat::from_axis_angle(-Vec3::X, camera.y);
+        transform.translation =
+            (rot * Vec3::new(0.0, 1.0, 0.0)) * camera.distance + camera.center;
+        transform.look_at(camera.center, Vec3::Y);
+    }
+
+    // Record only the frames the sweep itself produces.
+    if let Some(frame_time) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        .and_then(|diagnostic| diagnostic.value())
+    {
+        benchmark.samples.push(frame_time as f32);
+    }
+
+    benchmark.frame += 1;
+}
+
+/// Print mean/min/max plus the 1% and 0.1% low frame times, and optionally dump
+/// the raw per-frame samples to the CSV path given on the command line.
+fn report_benchmark(benchmark: &BenchmarkState) {
+    if benchmark.samples.is_empty() {
+        println!("Benchmark produced no samples.");
+        return;
+    }
+
+    let mut sorted = benchmark.samples.clone();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len();
+
+    let mean = sorted.iter().sum::<f32>() / n as f32;
+    let min = sorted[0];
+    let max = sorted[n - 1];
+    // Frame times are sorted ascending, so the high (worst) end holds the lows.
+    let p99 = sorted[((n - 1) as f32 * 0.99) as usize];
+    let p999 = sorted[((n - 1) as f32 * 0.999) as usize];
+
+    println!("Benchmark complete over {} frames (frame time in ms):", n);
+    println!("  mean:     {:.3}", mean);
+    println!("  min:      {:.3}", min);
+    println!("  max:      {:.3}", max);
+    println!("  1% low:   {:.3}", p99);
+    println!("  0.1% low: {:.3}", p999);
+
+    if let Some(path) = &benchmark.csv_path {
+        let mut contents = String::from("frame,frame_time_ms\n");
+        for (frame, sample) in benchmark.samples.iter().enumerate() {
+            contents.push_str(&format!("{},{}\n", frame, sample));
+        }
+        match std::fs::write(path, contents) {
+            Ok(()) => println!("Wrote {} samples to {}", benchmark.samples.len(), path),
+            Err(error) => eprintln!("Failed to write benchmark CSV to {}: {}", path, error),
+        }
+    }
+}
+
 fn parse_command_line_options(args: Vec<String>) -> StartupOptions {
     let mut options = StartupOptions {
         box_count: 6,
+        fly_camera: false,
+        scene_path: None,
+        scene_instances: 1,
+        skybox_path: None,
+        benchmark: false,
+        benchmark_csv: None,
     };
 
-    if args.len() > 1 {
-        options.box_count = args[1].parse().expect("Please specify the number of boxes as an integer.");
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--fly" => options.fly_camera = true,
+            "--benchmark" => options.benchmark = true,
+            "--csv" => {
+                options.benchmark_csv = Some(
+                    iter.next()
+                        .expect("Please specify a CSV output path after --csv.")
+                        .clone(),
+                );
+            }
+            "--skybox" => {
+                options.skybox_path = Some(
+                    iter.next()
+                        .expect("Please specify a cubemap PNG path after --skybox.")
+                        .clone(),
+                );
+            }
+            "--scene" => {
+                options.scene_path = Some(
+                    iter.next()
+                        .expect("Please specify a .gltf/.glb path after --scene.")
+                        .clone(),
+                );
+            }
+            "--instances" => {
+                options.scene_instances = iter
+                    .next()
+                    .expect("Please specify the number of scene instances after --instances.")
+                    .parse()
+                    .expect("Please specify the number of scene instances as an integer.");
+            }
+            other => {
+                options.box_count = other.parse().expect("Please specify the number of boxes as an integer.");
+            }
+        }
     }
 
-    return  options;
+    options
 }
 
 #[bevy_main]
 fn main() {
 	let args: Vec<String> = env::args().collect();
     let startup_options = parse_command_line_options(args);
-	App::build()
-		.add_resource(WindowDescriptor {
-			width: 800.0,
-			height: 600.0,
-			vsync: true,
-			decorations: false,
+	let benchmark_state = BenchmarkState {
+		running: startup_options.benchmark,
+		total_frames: BENCHMARK_FRAMES,
+		csv_path: startup_options.benchmark_csv.clone(),
+		..Default::default()
+	};
+	App::new()
+		.insert_resource(Msaa::Sample4)
+		.insert_resource(startup_options)
+		.insert_resource(benchmark_state)
+		.init_resource::<SceneState>()
+		.init_resource::<CubemapState>()
+		.init_resource::<CursorGrab>()
+		.add_plugins(DefaultPlugins.set(WindowPlugin {
+			primary_window: Some(Window {
+				resolution: (800.0, 600.0).into(),
+				decorations: false,
+				..Default::default()
+			}),
 			..Default::default()
-		})
-		.add_resource(Msaa { samples: 4 })
-		.add_resource(startup_options)
-		.add_plugins(DefaultPlugins)
-		.add_plugin(OrbitCameraPlugin)
-		.add_plugin(FrameTimeDiagnosticsPlugin::default())
-		.add_startup_system(init.system())
-		.add_system(text_update_system.system())
+		}))
+		.add_plugins((OrbitCameraPlugin, FlyCameraPlugin, FrameTimeDiagnosticsPlugin))
+		.add_systems(Startup, init)
+		.add_systems(
+			Update,
+			(
+				text_update_system,
+				toggle_camera_mode,
+				scene_camera_system,
+				skybox_asset_system,
+				cursor_grab_system,
+				benchmark_system,
+			),
+		)
 		.run();
-}
\ No newline at end of file
+}
@@ -4,8 +4,11 @@ use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
 use bevy::render::camera::Camera;
 
+use crate::CursorGrab;
+
 const LINE_TO_PIXEL_RATIO: f32 = 0.1;
 
+#[derive(Component)]
 pub struct OrbitCamera {
     pub x: f32,
     pub y: f32,
@@ -18,6 +21,7 @@ pub struct OrbitCamera {
     pub min_zoom_distance: f32,
     pub max_polar_angle: f32,
     pub min_polar_angle: f32,
+    pub enabled: bool,
 }
 
 impl Default for OrbitCamera {
@@ -34,6 +38,7 @@ impl Default for OrbitCamera {
             min_zoom_distance: -1.0,
             max_polar_angle: 3.13,
             min_polar_angle: 0.01,
+            enabled: true,
         }
     }
 }
@@ -41,10 +46,10 @@ impl Default for OrbitCamera {
 impl OrbitCamera {
     pub fn new(x: f32, y: f32, dist: f32, center: Vec3) -> OrbitCamera {
         OrbitCamera {
-            x: x,
-            y: y,
+            x,
+            y,
             distance: dist,
-            center: center,
+            center,
             rotate_sensitivity: 1.0,
             zoom_sensitivity: 0.8,
             pan_sensitivity: 1.0,
@@ -52,6 +57,7 @@ impl OrbitCamera {
             min_zoom_distance: 8.0,
             max_polar_angle: 3.13,
             min_polar_angle: 0.01,
+            enabled: true,
         }
     }
 }
@@ -61,17 +67,21 @@ impl OrbitCameraPlugin {
     fn mouse_motion_system(
         time: Res<Time>,
         mut mouse_motion_events: EventReader<MouseMotion>,
-        mouse_button_input: Res<Input<MouseButton>>,
-        keyboard_input: Res<Input<KeyCode>>,
+        mouse_button_input: Res<ButtonInput<MouseButton>>,
+        keyboard_input: Res<ButtonInput<KeyCode>>,
+        cursor_grab: Res<CursorGrab>,
         mut query: Query<(&mut OrbitCamera, &mut Transform, &mut Camera)>,
     ) {
         let mut delta = Vec2::ZERO;
-        for event in mouse_motion_events.iter() {
+        for event in mouse_motion_events.read() {
             delta += event.delta;
         }
         for (mut camera, mut transform, _) in query.iter_mut() {
+            if !camera.enabled {
+                continue;
+            }
             // Shift + LMB = Drag
-            if keyboard_input.pressed(KeyCode::LShift) {
+            if keyboard_input.pressed(KeyCode::ShiftLeft) {
                 if mouse_button_input.pressed(MouseButton::Left) {
                     let camera_translation = Vec3::new(
                         delta.x * camera.pan_sensitivity * time.delta_seconds(),
@@ -84,13 +94,13 @@ impl OrbitCameraPlugin {
                 }
             }
             else {
-                // LMB = Rotate around target
-                if mouse_button_input.pressed(MouseButton::Left) {
+                // LMB (or a grabbed cursor) = Rotate around target
+                if mouse_button_input.pressed(MouseButton::Left) || cursor_grab.grabbed {
                     camera.x -= delta.x * camera.rotate_sensitivity * time.delta_seconds();
                     camera.y -= delta.y * camera.rotate_sensitivity * time.delta_seconds();
-    
+
                     camera.y = camera.y.clamp(camera.min_polar_angle, camera.max_polar_angle);
-    
+
                     let rot = Quat::from_axis_angle(Vec3::Y, camera.x)
                         * Quat::from_axis_angle(-Vec3::X, camera.y);
                     transform.translation =
@@ -109,7 +119,7 @@ impl OrbitCameraPlugin {
                     camera.center += camera_translation;
                 }
             }
-            
+
         }
     }
 
@@ -118,13 +128,13 @@ impl OrbitCameraPlugin {
         query: Query<(&mut OrbitCamera, &mut Transform, &mut Camera)>,
     ) {
         let mut total = 0.0;
-        for event in mouse_wheel_events.iter() {
+        for event in mouse_wheel_events.read() {
             total += event.y * match event.unit {
                     Line => 1.0,
                     Pixel => LINE_TO_PIXEL_RATIO,
                 };
         }
-        Self::set_zoom_level(total, query);    
+        Self::set_zoom_level(total, query);
     }
 
     fn set_zoom_level(
@@ -132,9 +142,12 @@ impl OrbitCameraPlugin {
         mut query: Query<(&mut OrbitCamera, &mut Transform, &mut Camera)>,
     ){
         for (mut camera, mut transform, _) in query.iter_mut() {
+            if !camera.enabled {
+                continue;
+            }
             camera.distance *= camera.zoom_sensitivity.powf(zoom);
             camera.distance = camera.distance.clamp(
-                 camera.min_zoom_distance, 
+                 camera.min_zoom_distance,
                  camera.max_zoom_distance
             );
             let translation = &mut transform.translation;
@@ -144,24 +157,29 @@ impl OrbitCameraPlugin {
     }
 
     fn keyboard_controls_system(
-        keyboard_input: Res<Input<KeyCode>>,
+        keyboard_input: Res<ButtonInput<KeyCode>>,
         query: Query<(&mut OrbitCamera, &mut Transform, &mut Camera)>,
     ){
         let mut total = 0.0;
-        if keyboard_input.pressed(KeyCode::Up){
+        if keyboard_input.pressed(KeyCode::ArrowUp){
             total += 0.2;
             Self::set_zoom_level(total, query);
         }
-        else if keyboard_input.pressed(KeyCode::Down){
+        else if keyboard_input.pressed(KeyCode::ArrowDown){
             total -= 0.2;
             Self::set_zoom_level(total, query);
-        }   
+        }
     }
 }
 impl Plugin for OrbitCameraPlugin {
-    fn build(&self, app: &mut AppBuilder) {
-        app.add_system(Self::mouse_motion_system.system())
-            .add_system(Self::mouse_zoom_system.system())
-            .add_system(Self::keyboard_controls_system.system());
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                Self::mouse_motion_system,
+                Self::mouse_zoom_system,
+                Self::keyboard_controls_system,
+            ),
+        );
     }
-}
\ No newline at end of file
+}